@@ -0,0 +1,233 @@
+//! Unified diff rendering for `--check`/`--diff`
+//!
+//! Wrapping can turn one logical line into two, so a naive line-by-line
+//! comparison misreports hunk positions once a file has any inserted or
+//! removed lines. This module walks an LCS-based alignment instead,
+//! tracking separate original/new line counters so `@@ -a,b +c,d @@`
+//! headers stay accurate.
+
+use std::io::IsTerminal;
+
+/// One aligned line in a diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiffLine<'a> {
+    /// A line present, unchanged, on both sides
+    Context(&'a str),
+    /// A line only present in the original
+    Removed(&'a str),
+    /// A line only present in the formatted output
+    Added(&'a str),
+}
+
+impl<'a> DiffLine<'a> {
+    /// The line's text, regardless of which side it came from
+    pub(crate) const fn text(self) -> &'a str {
+        match self {
+            Self::Context(t) | Self::Removed(t) | Self::Added(t) => t,
+        }
+    }
+}
+
+/// One entry in the aligned diff, annotated with the original/new line
+/// number it corresponds to
+pub(crate) struct Entry<'a> {
+    pub(crate) kind: DiffLine<'a>,
+    pub(crate) old_ln: usize,
+    pub(crate) new_ln: usize,
+}
+
+/// Align `original` and `formatted` via their longest common subsequence
+/// of lines, producing a minimal sequence of context/removed/added lines
+pub(crate) fn lcs_diff<'a>(
+    original: &[&'a str],
+    formatted: &[&'a str],
+) -> Vec<DiffLine<'a>> {
+    let n = original.len();
+    let m = formatted.len();
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if original[i] == formatted[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if original[i] == formatted[j] {
+            lines.push(DiffLine::Context(original[i]));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            lines.push(DiffLine::Removed(original[i]));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(formatted[j]));
+            j += 1;
+        }
+    }
+    while i < n {
+        lines.push(DiffLine::Removed(original[i]));
+        i += 1;
+    }
+    while j < m {
+        lines.push(DiffLine::Added(formatted[j]));
+        j += 1;
+    }
+    lines
+}
+
+/// Walk an aligned diff, stamping each entry with the original/new line
+/// number it occupies
+pub(crate) fn annotate(diff: Vec<DiffLine<'_>>) -> Vec<Entry<'_>> {
+    let mut old_ln = 1;
+    let mut new_ln = 1;
+    let mut entries = Vec::with_capacity(diff.len());
+    for kind in diff {
+        entries.push(Entry { kind, old_ln, new_ln });
+        match kind {
+            DiffLine::Context(_) => {
+                old_ln += 1;
+                new_ln += 1;
+            }
+            DiffLine::Removed(_) => old_ln += 1,
+            DiffLine::Added(_) => new_ln += 1,
+        }
+    }
+    entries
+}
+
+/// Group annotated diff entries into hunks, keeping up to `context` lines
+/// of unchanged context around each run of changes and merging hunks whose
+/// context would otherwise overlap
+fn group_hunks<'a, 'b>(
+    entries: &'b [Entry<'a>],
+    context: usize,
+) -> Vec<&'b [Entry<'a>]> {
+    let changed: Vec<usize> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e.kind, DiffLine::Context(_)))
+        .map(|(i, _)| i)
+        .collect();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for c in changed {
+        let start = c.saturating_sub(context);
+        let end = (c + context + 1).min(entries.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => ranges.push((start, end)),
+        }
+    }
+    ranges.into_iter().map(|(s, e)| &entries[s..e]).collect()
+}
+
+/// Render one hunk as `@@ -a,b +c,d @@` plus its `+`/`-`/context lines,
+/// colorizing added/removed lines when `color` is set
+fn render_hunk(entries: &[Entry<'_>], color: bool) -> String {
+    let old_start = entries[0].old_ln;
+    let new_start = entries[0].new_ln;
+    let old_count =
+        entries.iter().filter(|e| !matches!(e.kind, DiffLine::Added(_))).count();
+    let new_count = entries
+        .iter()
+        .filter(|e| !matches!(e.kind, DiffLine::Removed(_)))
+        .count();
+    let mut out =
+        format!("@@ -{old_start},{old_count} +{new_start},{new_count} @@\n");
+    for entry in entries {
+        let (prefix, text, color_code) = match entry.kind {
+            DiffLine::Context(t) => (" ", t, None),
+            DiffLine::Removed(t) => ("-", t, Some("31")),
+            DiffLine::Added(t) => ("+", t, Some("32")),
+        };
+        match (color, color_code) {
+            (true, Some(code)) => {
+                out += &format!("\x1b[{code}m{prefix}{text}\x1b[0m\n");
+            }
+            _ => out += &format!("{prefix}{text}\n"),
+        }
+    }
+    out
+}
+
+/// Split `original`/`formatted` into lines and return their aligned,
+/// line-numbered diff. Shared by the unified-diff renderer and the
+/// `--emit` mismatch reporter so both work off the same alignment instead
+/// of each re-implementing line matching
+pub(crate) fn align<'a>(
+    original: &'a str,
+    formatted: &'a str,
+) -> Vec<Entry<'a>> {
+    let orig_lines: Vec<&str> = original.lines().collect();
+    let fmt_lines: Vec<&str> = formatted.lines().collect();
+    annotate(lcs_diff(&orig_lines, &fmt_lines))
+}
+
+/// Render a unified diff between `original` and `formatted`, with correct
+/// original/new line numbers in every hunk header, colorizing `+`/`-`
+/// lines when `color` is set
+pub fn unified_diff(
+    file: &str,
+    original: &str,
+    formatted: &str,
+    color: bool,
+) -> String {
+    let entries = align(original, formatted);
+    let hunks = group_hunks(&entries, 3);
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let mut out = format!("--- {file}\n+++ {file}\n");
+    for hunk in hunks {
+        out += &render_hunk(hunk, color);
+    }
+    out
+}
+
+/// Render a unified diff for `file`, colorizing it when STDOUT is a TTY
+pub fn diff_file(file: &str, original: &str, formatted: &str) -> String {
+    unified_diff(file, original, formatted, std::io::stdout().is_terminal())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lcs_diff_aligns_an_inserted_line_instead_of_shifting_everything_after_it() {
+        let original = ["one", "two three four", "five"];
+        let formatted = ["one", "two three", "four", "five"];
+        let diff = lcs_diff(&original, &formatted);
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Context("one"),
+                DiffLine::Removed("two three four"),
+                DiffLine::Added("two three"),
+                DiffLine::Added("four"),
+                DiffLine::Context("five"),
+            ]
+        );
+    }
+
+    #[test]
+    fn hunk_line_numbers_stay_correct_when_wrapping_inserts_a_line() {
+        let original = "one\ntwo three four\nfive\n";
+        let formatted = "one\ntwo three\nfour\nfive\n";
+        let diff = unified_diff("doc.tex", original, formatted, false);
+        // "five" is original line 3 but new line 4; the hunk header and
+        // trailing context line must reflect that, not a naive +1 shift.
+        assert!(diff.contains("@@ -1,3 +1,4 @@"), "{diff}");
+        assert!(diff.contains("\n five\n"), "{diff}");
+    }
+
+    #[test]
+    fn unified_diff_is_empty_when_nothing_changed() {
+        let text = "one\ntwo\n";
+        assert_eq!(unified_diff("doc.tex", text, text, false), "");
+    }
+}
@@ -0,0 +1,196 @@
+//! Machine-readable output for `--check`, modeled on rustfmt's `--emit`
+
+use crate::diff::DiffLine;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+
+/// Emission format for `--check` results
+#[allow(missing_docs)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum,
+)]
+pub enum EmitMode {
+    /// List the files that would change, one per line (the previous default)
+    Files,
+    /// Print the formatted text to STDOUT rather than rewriting the file
+    Stdout,
+    /// Emit a JSON array of mismatch records per file
+    Json,
+    /// Emit a checkstyle XML report, one `<error>` per contiguous run of
+    /// changed lines
+    Checkstyle,
+}
+
+/// A single line that differs between the original and formatted text
+#[derive(Debug, Clone, Serialize)]
+pub struct LineMismatch {
+    /// Line number in the original file
+    pub line: usize,
+    /// The original text of the line
+    pub original: String,
+    /// The text tex-fmt would write instead
+    pub formatted: String,
+}
+
+/// All mismatches found in one file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMismatches {
+    /// The file these mismatches belong to
+    pub file: String,
+    /// Every line that would change
+    pub mismatches: Vec<LineMismatch>,
+}
+
+/// Diff `original` and `formatted`, recording every line that differs.
+/// Built on the same LCS alignment the unified-diff renderer in
+/// [`crate::diff`] uses, rather than zipping the two texts line-by-line,
+/// so an inserted or removed line doesn't desynchronize every line after
+/// it and report the wrong line numbers
+pub fn diff_lines(
+    file: &str,
+    original: &str,
+    formatted: &str,
+) -> FileMismatches {
+    let entries = crate::diff::align(original, formatted);
+    let mismatches = pair_changes(&entries);
+    FileMismatches { file: file.to_owned(), mismatches }
+}
+
+/// Walk the aligned diff and collapse each contiguous run of
+/// removed/added lines into a single `LineMismatch`. A run is one logical
+/// change — e.g. wrapping splitting one line into two is 1 removed + 2
+/// added lines — so it is reported as one record, with the original and
+/// formatted sides joined by `\n` when either side has more than one
+/// line, rather than one record per line within the run (which would
+/// fabricate mismatches with an empty, misleading original/formatted
+/// side).
+fn pair_changes(entries: &[crate::diff::Entry<'_>]) -> Vec<LineMismatch> {
+    let mut mismatches = Vec::new();
+    let mut i = 0;
+    while i < entries.len() {
+        if matches!(entries[i].kind, DiffLine::Context(_)) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < entries.len()
+            && !matches!(entries[i].kind, DiffLine::Context(_))
+        {
+            i += 1;
+        }
+        let run = &entries[start..i];
+        let removed: Vec<_> = run
+            .iter()
+            .filter(|e| matches!(e.kind, DiffLine::Removed(_)))
+            .collect();
+        let added: Vec<_> = run
+            .iter()
+            .filter(|e| matches!(e.kind, DiffLine::Added(_)))
+            .collect();
+        // A run that only adds lines is reported at the original line it
+        // follows (or line 1 when it comes first)
+        let line = removed.first().map_or_else(
+            || added[0].old_ln.saturating_sub(1).max(1),
+            |e| e.old_ln,
+        );
+        let original = removed
+            .iter()
+            .map(|e| e.kind.text())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let formatted = added
+            .iter()
+            .map(|e| e.kind.text())
+            .collect::<Vec<_>>()
+            .join("\n");
+        mismatches.push(LineMismatch { line, original, formatted });
+    }
+    mismatches
+}
+
+/// Render one file's mismatches in the requested `mode`. Returns an empty
+/// string when there is nothing to print in that mode, e.g. `Files` mode
+/// for a file with no mismatches, or `Stdout` mode, whose output is the
+/// formatted text itself and is printed by the caller. `Json` mode is not
+/// handled here: a JSON array has to cover every file at once, so batch
+/// results with [`emit_json`] instead once the whole run has finished
+pub fn emit_file(mode: EmitMode, result: &FileMismatches) -> String {
+    match mode {
+        EmitMode::Files => {
+            if result.mismatches.is_empty() {
+                String::new()
+            } else {
+                format!("{}\n", result.file)
+            }
+        }
+        EmitMode::Stdout | EmitMode::Json => String::new(),
+        EmitMode::Checkstyle => emit_checkstyle(result),
+    }
+}
+
+/// Render every file's mismatches as a single JSON array, as CI tooling
+/// expects from an `--emit json` run, rather than one JSON object per file
+/// concatenated into an invalid stream
+pub fn emit_json(results: &[FileMismatches]) -> String {
+    serde_json::to_string(results).unwrap_or_default()
+}
+
+/// Render one file's mismatches as a checkstyle XML report
+fn emit_checkstyle(result: &FileMismatches) -> String {
+    let mut out =
+        format!("<checkstyle><file name=\"{}\">", xml_escape(&result.file));
+    for m in &result.mismatches {
+        let message = xml_escape(&format!(
+            "Line differs from formatted output: `{}`",
+            m.original
+        ));
+        let _ = write!(
+            out,
+            "<error line=\"{}\" severity=\"warning\" message=\"{message}\" />",
+            m.line,
+        );
+    }
+    out += "</file></checkstyle>";
+    out
+}
+
+/// Escape the characters that are special in XML attribute values
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_lines_reports_accurate_numbers_when_wrapping_inserts_a_line() {
+        // Line 2 gets wrapped into two lines; a naive zip would then
+        // compare every following original line against the wrong
+        // formatted line.
+        let original = "one\ntwo three four\nfive\n";
+        let formatted = "one\ntwo three\nfour\nfive\n";
+        let result = diff_lines("doc.tex", original, formatted);
+        assert_eq!(result.mismatches.len(), 1);
+        let m = &result.mismatches[0];
+        assert_eq!(m.line, 2);
+        assert_eq!(m.original, "two three four");
+        assert_eq!(m.formatted, "two three\nfour");
+        // "five" must not show up as a spurious mismatch against "four".
+    }
+
+    #[test]
+    fn emit_json_produces_a_single_array_across_files() {
+        let results = vec![
+            diff_lines("a.tex", "old\n", "new\n"),
+            diff_lines("b.tex", "same\n", "same\n"),
+        ];
+        let json = emit_json(&results);
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(parsed.is_array());
+        assert_eq!(parsed.as_array().unwrap().len(), 2);
+    }
+}
@@ -1,13 +1,19 @@
 //! Utilities for reading the command line arguments
 
+use crate::emit::EmitMode;
 use crate::logging::*;
 use crate::regexes::*;
-use clap::Parser;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueSource};
 use log::Level::{Error, Trace};
 use log::LevelFilter;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Name of the project-level configuration file, discovered the same way
+/// rustfmt discovers `rustfmt.toml`
+const CONFIG_FILE_NAME: &str = "tex-fmt.toml";
 
 /// Command line arguments
 #[allow(missing_docs)]
@@ -45,10 +51,141 @@ pub struct Cli {
     pub usetabs: bool,
     #[arg(long, help = "Line length for wrapping", default_value_t = 80)]
     pub wrap: u8,
+    #[arg(
+        long,
+        help = "Path to a tex-fmt.toml config file, instead of discovering one"
+    )]
+    pub config: Option<String>,
+    #[arg(long, help = "Print the effective configuration and exit")]
+    pub print_config: bool,
+    #[arg(
+        long,
+        value_enum,
+        help = "Line ending style to use in output",
+        default_value = "auto"
+    )]
+    pub newline: NewlineStyle,
+    #[arg(
+        long,
+        value_enum,
+        help = "Diagnostic output format",
+        default_value = "human"
+    )]
+    pub format: DiagnosticFormat,
+    #[arg(
+        long,
+        value_enum,
+        help = "Output format for --check results",
+        default_value = "files"
+    )]
+    pub emit: EmitMode,
+    #[arg(
+        long,
+        help = "With --check, print a unified diff instead of just reporting unformatted files"
+    )]
+    pub diff: bool,
     #[clap(skip)]
     pub wrap_min: u8,
 }
 
+/// Diagnostic rendering style for warnings
+#[allow(missing_docs)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum,
+)]
+pub enum DiagnosticFormat {
+    /// Rich output with a source snippet and carets, similar to rustfmt
+    Human,
+    /// Terse, single-line warnings
+    Short,
+}
+
+/// Line ending style used when writing output
+#[allow(missing_docs)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum,
+)]
+pub enum NewlineStyle {
+    /// Detect the dominant style of the input and preserve it
+    Auto,
+    /// Force Unix-style `\n` line endings
+    Unix,
+    /// Force Windows-style `\r\n` line endings
+    Windows,
+    /// Use whatever is native to the host platform
+    Native,
+}
+
+impl NewlineStyle {
+    /// Resolve `Auto`/`Native` against the detected style of `text`, so the
+    /// rest of the formatter only ever has to deal with a concrete style
+    fn resolve(self, text: &str) -> Self {
+        match self {
+            Self::Auto => detect_newline_style(text),
+            Self::Native if cfg!(windows) => Self::Windows,
+            Self::Native => Self::Unix,
+            Self::Unix | Self::Windows => self,
+        }
+    }
+
+    /// The literal line ending this style writes to output
+    const fn line_ending(self) -> &'static str {
+        match self {
+            Self::Windows => "\r\n",
+            Self::Auto | Self::Unix | Self::Native => "\n",
+        }
+    }
+}
+
+/// Detect the dominant newline style of `text` by counting `\r\n` pairs
+/// against lone `\n` occurrences, preferring Windows only when it is
+/// strictly more common
+fn detect_newline_style(text: &str) -> NewlineStyle {
+    let bytes = text.as_bytes();
+    let mut windows = 0;
+    let mut bare = 0;
+    for i in 0..bytes.len() {
+        if bytes[i] == b'\n' {
+            if i > 0 && bytes[i - 1] == b'\r' {
+                windows += 1;
+            } else {
+                bare += 1;
+            }
+        }
+    }
+    if windows > bare {
+        NewlineStyle::Windows
+    } else {
+        NewlineStyle::Unix
+    }
+}
+
+/// Strip `\r` from `\r\n` pairs so the formatter's internal logic can work
+/// purely with `\n`, resolving `newline` against the detected style of
+/// `text` so it can be re-applied when the output is joined
+fn normalize_newlines(
+    text: String,
+    newline: NewlineStyle,
+) -> (String, NewlineStyle) {
+    let style = newline.resolve(&text);
+    if text.contains('\r') {
+        (text.replace("\r\n", "\n"), style)
+    } else {
+        (text, style)
+    }
+}
+
+/// Re-apply a newline style when joining formatted text back into a single
+/// string for output
+pub fn apply_newline_style(text: &str, style: NewlineStyle) -> String {
+    let ending = style.line_ending();
+    if ending == "\n" {
+        text.to_owned()
+    } else {
+        text.replace('\n', ending)
+    }
+}
+
 impl Cli {
     /// Get the log level
     pub const fn log_level(&self) -> LevelFilter {
@@ -95,6 +232,17 @@ impl Cli {
         exit_code
     }
 
+    /// Print the effective configuration, after merging any config file, as
+    /// TOML
+    pub fn print_effective_config(&self) {
+        match toml::to_string_pretty(self) {
+            Ok(text) => print!("{text}"),
+            Err(e) => {
+                eprintln!("Could not serialize effective configuration: {e}");
+            }
+        }
+    }
+
     #[cfg(test)]
     pub const fn new() -> Self {
         Self {
@@ -109,13 +257,207 @@ impl Cli {
             tab: 2,
             usetabs: false,
             wrap: 80,
+            config: None,
+            print_config: false,
+            newline: NewlineStyle::Auto,
+            format: DiagnosticFormat::Human,
+            emit: EmitMode::Files,
+            diff: false,
             wrap_min: 70,
         }
     }
 }
 
-/// Add a missing extension and read the file
-pub fn read(file: &str, logs: &mut Vec<Log>) -> Option<(String, String)> {
+/// Which of [`Cli`]'s config-file-overridable fields were explicitly
+/// passed on the command line, as opposed to left at their default value.
+/// A boolean/literal comparison against the default can't tell `--wrap 80`
+/// apart from not passing `--wrap` at all, so this is derived from clap's
+/// `ArgMatches` instead, and used to make sure a config file only fills in
+/// the fields the user didn't set
+pub struct Explicit {
+    check: bool,
+    print: bool,
+    keep: bool,
+    verbose: bool,
+    quiet: bool,
+    trace: bool,
+    tab: bool,
+    usetabs: bool,
+    wrap: bool,
+}
+
+impl Explicit {
+    /// Inspect `matches` for which arguments were actually supplied on the
+    /// command line
+    fn from_matches(matches: &ArgMatches) -> Self {
+        let from_cli = |name: &str| {
+            matches.value_source(name) == Some(ValueSource::CommandLine)
+        };
+        Self {
+            check: from_cli("check"),
+            print: from_cli("print"),
+            keep: from_cli("keep"),
+            verbose: from_cli("verbose"),
+            quiet: from_cli("quiet"),
+            trace: from_cli("trace"),
+            tab: from_cli("tab"),
+            usetabs: from_cli("usetabs"),
+            wrap: from_cli("wrap"),
+        }
+    }
+}
+
+/// Parse the command line into a [`Cli`] together with the [`Explicit`]
+/// record of which fields were actually passed, for use by
+/// [`load_and_resolve`]
+pub fn parse_cli() -> (Cli, Explicit) {
+    let matches = Cli::command().get_matches();
+    let explicit = Explicit::from_matches(&matches);
+    let cli = Cli::from_arg_matches(&matches)
+        .unwrap_or_else(|e| e.exit());
+    (cli, explicit)
+}
+
+/// Partial configuration loaded from a `tex-fmt.toml` file. Every field is
+/// optional so that a project only needs to set the options it cares about
+#[allow(missing_docs)]
+#[allow(clippy::missing_docs_in_private_items)]
+#[derive(Debug, Default, Deserialize)]
+struct FileConfig {
+    check: Option<bool>,
+    print: Option<bool>,
+    keep: Option<bool>,
+    verbose: Option<bool>,
+    quiet: Option<bool>,
+    trace: Option<bool>,
+    tab: Option<i8>,
+    usetabs: Option<bool>,
+    wrap: Option<u8>,
+}
+
+/// Walk up from `start` to the filesystem root looking for
+/// [`CONFIG_FILE_NAME`], mirroring how rustfmt locates `rustfmt.toml`
+fn find_config_file(start: &Path) -> Option<PathBuf> {
+    let mut dir = Some(start);
+    while let Some(d) = dir {
+        let candidate = d.join(CONFIG_FILE_NAME);
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+        dir = d.parent();
+    }
+    None
+}
+
+/// Find the config file that applies to a single input file: `--config`
+/// forces a specific file for every input, otherwise discovery starts from
+/// `file`'s own directory (or the current directory when reading from
+/// `--stdin`, in which case `file` is `None`), mirroring how rustfmt
+/// resolves `rustfmt.toml` independently for each file it formats
+fn discover_config_file(cli: &Cli, file: Option<&str>) -> Option<PathBuf> {
+    if let Some(path) = &cli.config {
+        return Some(PathBuf::from(path));
+    }
+    let Some(file) = file else {
+        return find_config_file(&std::env::current_dir().ok()?);
+    };
+    let dir = Path::new(file).parent()?;
+    let dir = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    find_config_file(dir)
+}
+
+/// Parse a `tex-fmt.toml` file into a partial configuration
+fn load_config_file(path: &Path, logs: &mut Vec<Log>) -> FileConfig {
+    let file = path.display().to_string();
+    match fs::read_to_string(path) {
+        Ok(text) => match toml::from_str(&text) {
+            Ok(config) => config,
+            Err(e) => {
+                record_file_log(
+                    logs,
+                    Error,
+                    &file,
+                    &format!("Could not parse config file: {e}"),
+                );
+                FileConfig::default()
+            }
+        },
+        Err(e) => {
+            record_file_log(
+                logs,
+                Error,
+                &file,
+                &format!("Could not read config file: {e}"),
+            );
+            FileConfig::default()
+        }
+    }
+}
+
+/// Merge a partial file configuration into `cli`, letting any argument
+/// `explicit` marks as passed on the command line win over the
+/// corresponding file value
+fn merge_config(cli: &mut Cli, explicit: &Explicit, file: FileConfig) {
+    if !explicit.check {
+        cli.check = file.check.unwrap_or(cli.check);
+    }
+    if !explicit.print {
+        cli.print = file.print.unwrap_or(cli.print);
+    }
+    if !explicit.keep {
+        cli.keep = file.keep.unwrap_or(cli.keep);
+    }
+    if !explicit.verbose {
+        cli.verbose = file.verbose.unwrap_or(cli.verbose);
+    }
+    if !explicit.quiet {
+        cli.quiet = file.quiet.unwrap_or(cli.quiet);
+    }
+    if !explicit.trace {
+        cli.trace = file.trace.unwrap_or(cli.trace);
+    }
+    if !explicit.tab {
+        cli.tab = file.tab.unwrap_or(cli.tab);
+    }
+    if !explicit.usetabs {
+        cli.usetabs = file.usetabs.unwrap_or(cli.usetabs);
+    }
+    if !explicit.wrap {
+        cli.wrap = file.wrap.unwrap_or(cli.wrap);
+    }
+}
+
+/// Discover and merge the project config file that applies to `file`
+/// (`None` for `--stdin`) into a copy of `cli`, then resolve the combined
+/// result. Since a `tex-fmt.toml` can sit next to any file in a batch, not
+/// just the first, this is called once per input file rather than once
+/// for the whole invocation
+pub fn load_and_resolve(
+    cli: &Cli,
+    explicit: &Explicit,
+    file: Option<&str>,
+    logs: &mut Vec<Log>,
+) -> (Cli, u8) {
+    let mut resolved = cli.clone();
+    if let Some(path) = discover_config_file(cli, file) {
+        let file_config = load_config_file(&path, logs);
+        merge_config(&mut resolved, explicit, file_config);
+    }
+    let exit_code = resolved.resolve(logs);
+    (resolved, exit_code)
+}
+
+/// Add a missing extension and read the file, normalizing its line endings
+/// and reporting the style to re-apply on output
+pub fn read(
+    file: &str,
+    newline: NewlineStyle,
+    logs: &mut Vec<Log>,
+) -> Option<(String, String, NewlineStyle)> {
     // check if file has an accepted extension
     let has_ext = EXTENSIONS.iter().any(|e| file.ends_with(e));
     // if no valid extension, try adding .tex
@@ -124,7 +466,8 @@ pub fn read(file: &str, logs: &mut Vec<Log>) -> Option<(String, String)> {
         new_file.push_str(".tex");
     };
     if let Ok(text) = fs::read_to_string(&new_file) {
-        return Some((new_file, text));
+        let (text, style) = normalize_newlines(text, newline);
+        return Some((new_file, text, style));
     }
     if has_ext {
         record_file_log(logs, Error, file, "Could not open file.");
@@ -134,8 +477,12 @@ pub fn read(file: &str, logs: &mut Vec<Log>) -> Option<(String, String)> {
     None
 }
 
-/// Attempt to read from STDIN, return filename `<STDIN>` and text
-pub fn read_stdin(logs: &mut Vec<Log>) -> Option<(String, String)> {
+/// Attempt to read from STDIN, return filename `<STDIN>`, the newline-
+/// normalized text, and the style to re-apply on output
+pub fn read_stdin(
+    newline: NewlineStyle,
+    logs: &mut Vec<Log>,
+) -> Option<(String, String, NewlineStyle)> {
     let mut text = String::new();
     match std::io::stdin().read_to_string(&mut text) {
         Ok(bytes) => {
@@ -145,7 +492,8 @@ pub fn read_stdin(logs: &mut Vec<Log>) -> Option<(String, String)> {
                 "<STDIN>",
                 &format!("Read {bytes} bytes."),
             );
-            Some((String::from("<STDIN>"), text))
+            let (text, style) = normalize_newlines(text, newline);
+            Some((String::from("<STDIN>"), text, style))
         }
         Err(e) => {
             record_file_log(
@@ -158,3 +506,48 @@ pub fn read_stdin(logs: &mut Vec<Log>) -> Option<(String, String)> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_windows_style_when_crlf_is_dominant() {
+        assert_eq!(
+            detect_newline_style("a\r\nb\r\nc\r\n"),
+            NewlineStyle::Windows
+        );
+    }
+
+    #[test]
+    fn detects_unix_style_when_lf_is_dominant() {
+        assert_eq!(detect_newline_style("a\nb\nc\n"), NewlineStyle::Unix);
+    }
+
+    #[test]
+    fn falls_back_to_unix_when_counts_are_tied() {
+        // One `\r\n` pair and one lone `\n`: not strictly more Windows,
+        // so Unix wins.
+        assert_eq!(detect_newline_style("a\r\nb\n"), NewlineStyle::Unix);
+    }
+
+    #[test]
+    fn normalize_strips_crlf_and_resolves_auto_to_the_detected_style() {
+        let (text, style) =
+            normalize_newlines("a\r\nb\r\n".to_owned(), NewlineStyle::Auto);
+        assert_eq!(text, "a\nb\n");
+        assert_eq!(style, NewlineStyle::Windows);
+    }
+
+    #[test]
+    fn apply_newline_style_reinserts_the_recorded_ending() {
+        assert_eq!(
+            apply_newline_style("a\nb\n", NewlineStyle::Windows),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(
+            apply_newline_style("a\nb\n", NewlineStyle::Unix),
+            "a\nb\n"
+        );
+    }
+}
@@ -6,22 +6,34 @@ use crate::format::*;
 use crate::logging::*;
 use crate::Config;
 use log::Level::{Trace, Warn};
+use unicode_width::UnicodeWidthChar;
+
+/// Sum the display width of `line`'s characters, treating control
+/// characters as zero-width and combining marks as zero-width so they
+/// never count towards the column limit
+fn display_width(line: &str) -> usize {
+    line.chars().map(|c| UnicodeWidthChar::width(c).unwrap_or(0)).sum()
+}
 
 /// Check if a line needs wrapping
 pub fn needs_wrap(line: &str, state: &State, args: &Config) -> bool {
     !args.keep
         && !state.verbatim.visual
         && !state.ignore.visual
-        && (line.chars().count() > args.wrap.into())
+        && (display_width(line) > args.wrap.into())
 }
 
-/// Find the best place to break a long line
+/// Find the best place to break a long line, measuring position in display
+/// columns rather than char count, then returning the char index where
+/// that column was reached so callers can keep slicing with
+/// `chars().take`/`skip`
 fn find_wrap_point(line: &str, args: &Config) -> Option<usize> {
     let mut wrap_point: Option<usize> = None;
     let mut after_char = false;
     let mut prev_char: Option<char> = None;
+    let mut col: usize = 0;
     for (i, c) in line.chars().enumerate() {
-        if i >= args.wrap_min.into() && wrap_point.is_some() {
+        if col >= args.wrap_min.into() && wrap_point.is_some() {
             break;
         }
         if c == ' ' && prev_char != Some('\\') {
@@ -32,10 +44,64 @@ fn find_wrap_point(line: &str, args: &Config) -> Option<usize> {
             after_char = true;
         }
         prev_char = Some(c);
+        col += UnicodeWidthChar::width(c).unwrap_or(0);
     }
     wrap_point
 }
 
+/// Find the char index of the first character whose span reaches past
+/// display column `width`, so a wide character straddling that column is
+/// the one returned, falling back to the end of the line if it is never
+/// that wide
+fn char_index_at_width(line: &str, width: usize) -> usize {
+    let mut col = 0;
+    for (i, c) in line.chars().enumerate() {
+        col += UnicodeWidthChar::width(c).unwrap_or(0);
+        if col > width {
+            return i;
+        }
+    }
+    line.chars().count()
+}
+
+/// Render a rich, rustfmt-style diagnostic for a line that could not be
+/// wrapped: a caret underlining the column where it first exceeds
+/// `args.wrap`, and, when a partial candidate break exists, a secondary
+/// annotation pointing at it. `record_line_log` already prints the
+/// offending `line` itself after a `file:linum_new: ` prefix, so the
+/// caret/annotation rows are indented by that same prefix width (plus the
+/// offending column's display width, not char count) to land under the
+/// right column of the line as it is actually rendered, not column 0
+fn render_wrap_diagnostic(
+    file: &str,
+    state: &State,
+    line: &str,
+    wrap_point: Option<usize>,
+    args: &Config,
+) -> String {
+    let prefix_width =
+        format!("{file}:{}: ", state.linum_new).chars().count();
+    let overflow_col: usize = args.wrap.into();
+    let overflow_idx = char_index_at_width(line, overflow_col);
+    let underline_len = display_width(
+        &line.chars().skip(overflow_idx).collect::<String>(),
+    )
+    .max(1);
+    let mut message = format!(
+        "Line cannot be wrapped.\n{}{}",
+        " ".repeat(prefix_width + overflow_col),
+        "^".repeat(underline_len)
+    );
+    if let Some(p) = wrap_point {
+        let break_col = display_width(&line.chars().take(p).collect::<String>());
+        message += &format!(
+            "\n{}- only candidate break found here",
+            " ".repeat(prefix_width + break_col)
+        );
+    }
+    message
+}
+
 /// Wrap a long line into a short prefix and a suffix
 pub fn apply_wrap(
     line: &str,
@@ -59,8 +125,16 @@ pub fn apply_wrap(
     let comment_index = find_comment_index(line);
 
     match wrap_point {
-        Some(p) if p <= args.wrap.into() => {}
+        Some(p)
+            if display_width(&line.chars().take(p).collect::<String>())
+                <= args.wrap.into() => {}
         _ => {
+            let message = match args.format {
+                DiagnosticFormat::Human => {
+                    render_wrap_diagnostic(file, state, line, wrap_point, args)
+                }
+                DiagnosticFormat::Short => "Line cannot be wrapped.".to_owned(),
+            };
             record_line_log(
                 logs,
                 Warn,
@@ -68,7 +142,7 @@ pub fn apply_wrap(
                 state.linum_new,
                 state.linum_old,
                 line,
-                "Line cannot be wrapped.",
+                &message,
             );
         }
     };
@@ -82,3 +156,41 @@ pub fn apply_wrap(
         (line_1, line_2)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_width_counts_ascii_as_one_column_each() {
+        assert_eq!(display_width("hello"), 5);
+    }
+
+    #[test]
+    fn display_width_counts_wide_cjk_characters_as_two_columns() {
+        assert_eq!(display_width("\u{4e2d}\u{6587}"), 4);
+    }
+
+    #[test]
+    fn display_width_ignores_combining_marks() {
+        // 'e' followed by a combining acute accent (U+0301) renders as one
+        // column, not two.
+        assert_eq!(display_width("e\u{0301}"), 1);
+    }
+
+    #[test]
+    fn char_index_at_width_accounts_for_wide_characters() {
+        // Two wide CJK characters (width 2 each) followed by ASCII: the
+        // 3rd display column falls inside the 2nd character, so the char
+        // index returned must still be 1 (not 2, which would skip past the
+        // character straddling that column).
+        let line = "\u{4e2d}\u{6587}ab";
+        assert_eq!(char_index_at_width(line, 3), 1);
+        assert_eq!(char_index_at_width(line, 4), 2);
+    }
+
+    #[test]
+    fn char_index_at_width_falls_back_to_the_end_of_a_short_line() {
+        assert_eq!(char_index_at_width("abc", 80), 3);
+    }
+}